@@ -3,22 +3,33 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use tui::layout::{Constraint, Layout};
+use tui::layout::{Constraint, Direction, Layout};
 use tui::style::{Color, Style};
-use tui::widgets::{Block, Borders, Row, Table, TableState};
+use tui::text::{Span, Spans};
+use tui::widgets::canvas::{self, Canvas};
+use tui::widgets::{
+    Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph, Row, Table, TableState, Tabs,
+};
+use tui::symbols;
 use tui::{backend::CrosstermBackend, Terminal};
 use std::fs::File;
 use std::{
+    cmp::Ordering,
     io::{stdout, Write},
-    sync::mpsc,
     thread,
     time::Duration,
 };
 use crossterm::event::MouseEvent;
 use roxmltree::Node;
+use tokio::sync::watch;
 
 mod net;
+mod refresh;
 mod state;
+mod storage;
+
+use refresh::{ObsSnapshot, RealtimeSnapshot, StationsSnapshot};
+use state::{MapViewport, TabsState};
 
 pub enum Event<I,J> {
     Key(I),
@@ -28,20 +39,29 @@ pub enum Event<I,J> {
 pub struct StatefulTable {
     state: TableState,
     items: Vec<Vec<String>>,
+    filtered: Vec<usize>,
+    query: String,
 }
 
 impl StatefulTable {
     fn new(rows: Vec<Vec<String>>) -> StatefulTable {
+        let filtered = (0..rows.len()).collect();
 
         StatefulTable {
             state: TableState::default(),
             items: rows,
+            filtered,
+            query: String::new(),
         }
     }
     pub fn next(&mut self) {
+        if self.filtered.is_empty() {
+            self.state.select(None);
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
-                if i >= self.items.len() - 1 {
+                if i >= self.filtered.len() - 1 {
                     0
                 } else {
                     i + 1
@@ -53,10 +73,14 @@ impl StatefulTable {
     }
 
     pub fn previous(&mut self) {
+        if self.filtered.is_empty() {
+            self.state.select(None);
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.items.len() - 1
+                    self.filtered.len() - 1
                 } else {
                     i - 1
                 }
@@ -65,19 +89,113 @@ impl StatefulTable {
         };
         self.state.select(Some(i));
     }
+
+    /// Replaces the item set with a fresh snapshot, clamping the current
+    /// selection so a refresh that shrinks the table doesn't leave a
+    /// dangling index.
+    pub fn set_items(&mut self, items: Vec<Vec<String>>) {
+        self.items = items;
+        self.recompute_filter();
+    }
+
+    /// Fuzzy-filters on the id (column 0) and name (column 1) fields,
+    /// keeping the full item set intact so clearing the query restores
+    /// every row.
+    pub fn set_filter(&mut self, query: String) {
+        self.query = query;
+        self.recompute_filter();
+    }
+
+    /// The row currently selected, mapped back through the active filter.
+    pub fn selected_item(&self) -> Option<&Vec<String>> {
+        self.state
+            .selected()
+            .and_then(|i| self.filtered.get(i))
+            .and_then(|&i| self.items.get(i))
+    }
+
+    /// Selects the row whose first column equals `value`, clearing any
+    /// active filter first so the row is guaranteed to be in view. Used to
+    /// jump back to a station picked on the map.
+    pub fn select_by_first_column(&mut self, value: &str) {
+        self.query.clear();
+        self.recompute_filter();
+        if let Some(pos) = self
+            .filtered
+            .iter()
+            .position(|&i| self.items[i].first().map(String::as_str) == Some(value))
+        {
+            self.state.select(Some(pos));
+        }
+    }
+
+    /// The rows that should currently be rendered, in display order.
+    fn displayed(&self) -> Vec<&Vec<String>> {
+        self.filtered.iter().map(|&i| &self.items[i]).collect()
+    }
+
+    fn recompute_filter(&mut self) {
+        self.filtered = if self.query.is_empty() {
+            (0..self.items.len()).collect()
+        } else {
+            self.items
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| {
+                    row.get(0).map_or(false, |id| fuzzy_match(&self.query, id))
+                        || row.get(1).map_or(false, |name| fuzzy_match(&self.query, name))
+                })
+                .map(|(i, _)| i)
+                .collect()
+        };
+
+        match (self.state.selected(), self.filtered.len()) {
+            (_, 0) => self.state.select(None),
+            (Some(i), len) if i >= len => self.state.select(Some(len - 1)),
+            (None, len) if len > 0 => self.state.select(Some(0)),
+            _ => {}
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), failure::Error> {
-    let active_stations_resp = net::get_active_stations().await.unwrap();
+    // Persistence is a nice-to-have, not a hard requirement: if the DB file
+    // can't be opened (permissions, disk full, locked by another instance),
+    // fall back to running without history instead of crashing the TUI.
+    let db = match storage::open(&storage::default_db_path()) {
+        Ok(conn) => Some(std::sync::Arc::new(std::sync::Mutex::new(conn))),
+        Err(e) => {
+            eprintln!("failed to open observation store, running without persistence: {}", e);
+            None
+        }
+    };
 
-    let body = active_stations_resp.text().await.unwrap();
-    let active_stations = xml_to_stations(body);
-    //let mut rows = Vec::new();
-    let rows: Vec<Vec<String>> = active_stations.iter().map(|a| a.to_row()).collect();
+    let (stations_tx, mut stations_rx) = watch::channel(StationsSnapshot::default());
+    let (obs_tx, mut obs_rx) = watch::channel(ObsSnapshot::default());
+    let (id_tx, id_rx) = watch::channel(None::<String>);
+    let (realtime_tx, mut realtime_rx) = watch::channel(RealtimeSnapshot::default());
+    let (history_tx, mut history_rx) = watch::channel(RealtimeSnapshot::default());
 
-    //let split = body.split('\n');
-    //let row_strs = split.collect::<Vec<&str>>();
+    refresh::spawn_stations_refresher(stations_tx);
+    refresh::spawn_obs_refresher(obs_tx, db.clone());
+    refresh::spawn_realtime_refresher(id_rx, realtime_tx);
+    refresh::spawn_history_refresher(id_tx.subscribe(), db, history_tx);
+
+    // Wait for the first round of data before entering the UI, so the user
+    // doesn't stare at empty tables while the initial fetches are in flight.
+    // The refreshers also publish when a fetch starts (refreshing = true),
+    // so keep waiting until a fetch has finished with data or an error.
+    while { let s = stations_rx.borrow(); s.last_updated.is_none() && s.error.is_none() } {
+        if stations_rx.changed().await.is_err() {
+            break;
+        }
+    }
+    while { let s = obs_rx.borrow(); s.last_updated.is_none() && s.error.is_none() } {
+        if obs_rx.changed().await.is_err() {
+            break;
+        }
+    }
 
     enable_raw_mode()?;
 
@@ -89,7 +207,7 @@ async fn main() -> Result<(), failure::Error> {
     let mut terminal = Terminal::new(backend)?;
     terminal.hide_cursor()?;
 
-    let (tx, rx) = mpsc::channel();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
 
     thread::spawn(move || {
         let mut evt_file = File::create("evt.log").unwrap();
@@ -107,98 +225,663 @@ async fn main() -> Result<(), failure::Error> {
         }
     });
 
-    let mut table = StatefulTable::new(rows);
+    let mut table = StatefulTable::new(Vec::new());
+    let mut obs_table = StatefulTable::new(Vec::new());
+    let mut tabs = TabsState::new(vec!["Active Stations", "Latest Observations", "Detail", "Chart", "Map"]);
+    let mut chart_variable = ChartVariable::WaveHeight;
+    let mut map_viewport = MapViewport::new();
+    let mut last_sent_id: Option<String> = None;
+    let mut search_mode = false;
 
-    // Input
     loop {
+        table.set_items(stations_rx.borrow().stations.iter().map(|a| a.to_row()).collect());
+        obs_table.set_items(obs_rx.borrow().observations.iter().map(|o| o.to_row()).collect());
+
+        let selected_id = table.selected_item().and_then(|row| row.first().cloned());
+        if selected_id != last_sent_id {
+            id_tx.send(selected_id.clone()).ok();
+            last_sent_id = selected_id.clone();
+        }
+
         terminal.draw(|mut f| {
             let rects = Layout::default()
-                .constraints([Constraint::Percentage(100)].as_ref())
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Length(3),
+                        Constraint::Min(0),
+                        Constraint::Length(1),
+                        Constraint::Length(1),
+                    ]
+                    .as_ref(),
+                )
                 .margin(2)
                 .split(f.size());
 
-            let selected_style = Style::default().fg(Color::LightCyan);
-            let normal_style = Style::default().fg(Color::White);
+            let titles: Vec<Spans> = tabs.titles.iter().map(|t| Spans::from(*t)).collect();
+            let tabs_widget = Tabs::new(titles)
+                .block(Block::default().borders(Borders::ALL).title("tuoy"))
+                .select(tabs.index)
+                .highlight_style(Style::default().fg(Color::LightCyan));
+            f.render_widget(tabs_widget, rects[0]);
 
-//            let header = [
-//                "stn", "lat", "lon", "year", "mo", "day", "hr", "min", "wdir", "wspd", "gst",
-//                "wvht", "dpd", "apd", "mwd", "pres", "ptdy", "atmp", "wtmp", "dewp", "vis", "tide",
-//            ];
-            let header = ["station", "name", "lat", "lon", "program", "kind", "met", "currents", "water quality", "dart"];
-            let rows = table
-                .items
-                .iter()
-                .map(|i| Row::StyledData(i.iter(), normal_style));
-            let t = Table::new(header.iter(), rows)
-                .block(
-                    Block::default()
-                        //.borders(Borders::ALL)
-                        .title("Active Stations"),
-                )
-                .highlight_style(selected_style)
-                .widths(&[
-                    Constraint::Percentage(5),
-                    Constraint::Percentage(30),
-                    Constraint::Percentage(4),
-                    Constraint::Percentage(4),
-                    Constraint::Percentage(25),
-                    Constraint::Percentage(10),
-                    Constraint::Percentage(3),
-                    Constraint::Percentage(3),
-                    Constraint::Percentage(10),
-                    Constraint::Percentage(3),
-                ]);
-            f.render_stateful_widget(t, rects[0], &mut table.state);
+            let stations = stations_rx.borrow();
+            let obs = obs_rx.borrow();
+            let realtime = realtime_rx.borrow();
+            let history = history_rx.borrow();
+
+            // The live NDBC fetch only ever holds a couple of days of
+            // samples and is empty until the first poll lands; fall back to
+            // the locally persisted history so the chart has something to
+            // show regardless.
+            let chart_series = if realtime.series.series_for(chart_variable).is_empty() {
+                &history.series
+            } else {
+                &realtime.series
+            };
+
+            match tabs.index {
+                0 => draw_active_stations(&mut f, rects[1], &mut table),
+                1 => draw_latest_observations(&mut f, rects[1], &mut obs_table),
+                2 => draw_detail(&mut f, rects[1], &stations.stations, &obs.observations, selected_id.as_deref()),
+                3 => draw_chart(&mut f, rects[1], chart_series, chart_variable),
+                _ => draw_map(&mut f, rects[1], &stations.stations, selected_id.as_deref(), &map_viewport),
+            }
+
+            draw_search_bar(&mut f, rects[2], search_mode, &table.query);
+            draw_status_bar(&mut f, rects[3], &stations, &obs, &realtime);
         })?;
 
-        match rx.recv()? {
-            Event::Key(event) => match event.code {
-                KeyCode::Char('q') => {
-                    disable_raw_mode()?;
-                    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
-                    terminal.show_cursor()?;
-                    break;
-                }
-                KeyCode::Down => {
-                  
-                    table.next();
+        tokio::select! {
+            event = rx.recv() => {
+                match event {
+                    Some(Event::Key(event)) if search_mode => match event.code {
+                        KeyCode::Esc => {
+                            search_mode = false;
+                            table.set_filter(String::new());
+                        }
+                        KeyCode::Enter => {
+                            search_mode = false;
+                        }
+                        KeyCode::Backspace => {
+                            let mut query = table.query.clone();
+                            query.pop();
+                            table.set_filter(query);
+                        }
+                        KeyCode::Down => table.next(),
+                        KeyCode::Up => table.previous(),
+                        KeyCode::Char(c) => {
+                            let mut query = table.query.clone();
+                            query.push(c);
+                            table.set_filter(query);
+                        }
+                        _ => {}
+                    },
+                    Some(Event::Key(event)) => match event.code {
+                        KeyCode::Char('q') => {
+                            disable_raw_mode()?;
+                            execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+                            terminal.show_cursor()?;
+                            break;
+                        }
+                        KeyCode::Char('/') if tabs.index == 0 => {
+                            search_mode = true;
+                        }
+                        KeyCode::Esc => {
+                            table.set_filter(String::new());
+                        }
+                        KeyCode::Tab => {
+                            tabs.next();
+                        }
+                        KeyCode::BackTab => {
+                            tabs.previous();
+                        }
+                        KeyCode::Down => {
+                            match tabs.index {
+                                1 => obs_table.next(),
+                                4 => map_viewport.pan(0.0, -1.0),
+                                _ => table.next(),
+                            }
+                        }
+                        KeyCode::Up => {
+                            match tabs.index {
+                                1 => obs_table.previous(),
+                                4 => map_viewport.pan(0.0, 1.0),
+                                _ => table.previous(),
+                            }
+                        }
+                        KeyCode::Left if tabs.index == 4 => {
+                            map_viewport.pan(-1.0, 0.0);
+                        }
+                        KeyCode::Right if tabs.index == 4 => {
+                            map_viewport.pan(1.0, 0.0);
+                        }
+                        KeyCode::Char('+') if tabs.index == 4 => {
+                            map_viewport.zoom_in();
+                        }
+                        KeyCode::Char('-') if tabs.index == 4 => {
+                            map_viewport.zoom_out();
+                        }
+                        KeyCode::Enter if tabs.index == 4 => {
+                            let center = map_viewport.center();
+                            let nearest_id = stations_rx
+                                .borrow()
+                                .stations
+                                .iter()
+                                .min_by(|a, b| {
+                                    map_distance(a, center)
+                                        .partial_cmp(&map_distance(b, center))
+                                        .unwrap_or(Ordering::Equal)
+                                })
+                                .map(|s| s.id.clone());
+                            if let Some(id) = nearest_id {
+                                table.select_by_first_column(&id);
+                                tabs.index = 0;
+                            }
+                        }
+                        KeyCode::Char('v') if tabs.index == 3 => {
+                            chart_variable = chart_variable.next();
+                        }
+                        _ => {}
+                    },
+                    Some(Event::Mouse(event)) => match event {
+                        MouseEvent::ScrollDown(_, _, _) => {
+                            table.previous();
+                        },
+                        MouseEvent::ScrollUp(_, _, _) => {
+                            table.next();
+                        },
+                        _ => {},
+                    },
+                    None => break,
                 }
-                KeyCode::Up => {
-                   
-                    table.previous();
-                }
-                _ => {}
-            },
-            Event::Mouse(event) => match event{
-                MouseEvent::ScrollDown(_, _, _) => {
-                    
-                    table.previous();
-                },
-                MouseEvent::ScrollUp(_, _, _) => {
-                    table.next();
-                },
-                _ => {},
-            },
+            }
+            _ = stations_rx.changed() => {}
+            _ = obs_rx.changed() => {}
+            _ = realtime_rx.changed() => {}
+            _ = history_rx.changed() => {}
         }
     }
 
     Ok(())
 }
 
-fn xml_to_stations(body: String) -> Vec<ActiveStation>{
-    let doc = roxmltree::Document::parse(&body).unwrap();
-    let active_stations: Vec<ActiveStation> = doc.descendants()
-        .filter(|n| n.tag_name().name() == "station")
-        .map(|n| ActiveStation::from_node(n))
+fn draw_active_stations<B: tui::backend::Backend>(
+    f: &mut tui::terminal::Frame<B>,
+    area: tui::layout::Rect,
+    table: &mut StatefulTable,
+) {
+    let selected_style = Style::default().fg(Color::LightCyan);
+    let normal_style = Style::default().fg(Color::White);
+
+    let header = ["station", "name", "lat", "lon", "program", "kind", "met", "currents", "water quality", "dart"];
+    let query = table.query.clone();
+    let displayed: Vec<Vec<String>> = table
+        .displayed()
+        .iter()
+        .map(|row| highlight_matched_fields(&query, row))
         .collect();
-    active_stations
+    let rows = displayed
+        .iter()
+        .map(|i| Row::StyledData(i.iter(), normal_style));
+    let title = if query.is_empty() {
+        String::from("Active Stations")
+    } else {
+        format!("Active Stations (filter: {})", query)
+    };
+    let t = Table::new(header.iter(), rows)
+        .block(Block::default().title(title))
+        .highlight_style(selected_style)
+        .widths(&[
+            Constraint::Percentage(5),
+            Constraint::Percentage(30),
+            Constraint::Percentage(4),
+            Constraint::Percentage(4),
+            Constraint::Percentage(25),
+            Constraint::Percentage(10),
+            Constraint::Percentage(3),
+            Constraint::Percentage(3),
+            Constraint::Percentage(10),
+            Constraint::Percentage(3),
+        ]);
+    f.render_stateful_widget(t, area, &mut table.state);
+}
+
+fn draw_latest_observations<B: tui::backend::Backend>(
+    f: &mut tui::terminal::Frame<B>,
+    area: tui::layout::Rect,
+    table: &mut StatefulTable,
+) {
+    let selected_style = Style::default().fg(Color::LightCyan);
+    let normal_style = Style::default().fg(Color::White);
+
+    let header = [
+        "stn", "lat", "lon", "year", "mo", "day", "hr", "min", "wdir", "wspd", "gst",
+        "wvht", "dpd", "apd", "mwd", "pres", "ptdy", "atmp", "wtmp", "dewp", "vis", "tide",
+    ];
+    let rows = table
+        .items
+        .iter()
+        .map(|i| Row::StyledData(i.iter(), normal_style));
+    let t = Table::new(header.iter(), rows)
+        .block(Block::default().title("Latest Observations"))
+        .highlight_style(selected_style)
+        .widths(&[
+            Constraint::Percentage(6),  // stn
+            Constraint::Percentage(5),  // lat
+            Constraint::Percentage(5),  // lon
+            Constraint::Percentage(4),  // year
+            Constraint::Percentage(3),  // mo
+            Constraint::Percentage(3),  // day
+            Constraint::Percentage(3),  // hr
+            Constraint::Percentage(3),  // min
+            Constraint::Percentage(5),  // wdir
+            Constraint::Percentage(5),  // wspd
+            Constraint::Percentage(4),  // gst
+            Constraint::Percentage(5),  // wvht
+            Constraint::Percentage(4),  // dpd
+            Constraint::Percentage(4),  // apd
+            Constraint::Percentage(4),  // mwd
+            Constraint::Percentage(5),  // pres
+            Constraint::Percentage(4),  // ptdy
+            Constraint::Percentage(5),  // atmp
+            Constraint::Percentage(5),  // wtmp
+            Constraint::Percentage(4),  // dewp
+            Constraint::Percentage(4),  // vis
+            Constraint::Percentage(4),  // tide
+        ]);
+    f.render_stateful_widget(t, area, &mut table.state);
+}
+
+fn draw_detail<B: tui::backend::Backend>(
+    f: &mut tui::terminal::Frame<B>,
+    area: tui::layout::Rect,
+    active_stations: &[ActiveStation],
+    latest_obs: &[LatestObservation],
+    selected_id: Option<&str>,
+) {
+    let block = Block::default().borders(Borders::ALL).title("Detail");
+    let text = match selected_id.and_then(|id| active_stations.iter().find(|s| s.id == id)) {
+        Some(station) => {
+            let mut text = format!(
+                "station: {}\nname: {}\nlat: {}\nlon: {}\nprogram: {}\nkind: {}\nmet: {}\ncurrents: {}\nwater quality: {}\ndart: {}",
+                station.id,
+                station.name,
+                station.lat,
+                station.lon,
+                station.program,
+                station.kind,
+                station.met,
+                station.currents,
+                station.water_quality,
+                station.dart,
+            );
+            if let Some(obs) = latest_obs.iter().find(|o| o.stn == station.id) {
+                text.push_str(&format!(
+                    "\n\nlatest observation:\nwind dir: {}\nwind speed: {}\ngust: {}\nwave height: {}\npressure: {}\nair temp: {}\nwater temp: {}",
+                    fmt_opt(obs.wdir),
+                    fmt_opt(obs.wspd),
+                    fmt_opt(obs.gst),
+                    fmt_opt(obs.wvht),
+                    fmt_opt(obs.pres),
+                    fmt_opt(obs.atmp),
+                    fmt_opt(obs.wtmp),
+                ));
+            }
+            text
+        }
+        None => String::from("no station selected"),
+    };
+    let paragraph = Paragraph::new(text).block(block);
+    f.render_widget(paragraph, area);
+}
+
+#[derive(Clone, Copy)]
+enum ChartVariable {
+    WaveHeight,
+    WindSpeed,
+    Pressure,
+}
+
+impl ChartVariable {
+    fn next(self) -> ChartVariable {
+        match self {
+            ChartVariable::WaveHeight => ChartVariable::WindSpeed,
+            ChartVariable::WindSpeed => ChartVariable::Pressure,
+            ChartVariable::Pressure => ChartVariable::WaveHeight,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ChartVariable::WaveHeight => "wave height (m)",
+            ChartVariable::WindSpeed => "wind speed (m/s)",
+            ChartVariable::Pressure => "pressure (hPa)",
+        }
+    }
+}
+
+#[derive(Default)]
+struct RealtimeSeries {
+    wave_height: Vec<(f64, f64)>,
+    wind_speed: Vec<(f64, f64)>,
+    pressure: Vec<(f64, f64)>,
+}
+
+impl RealtimeSeries {
+    fn series_for(&self, variable: ChartVariable) -> &[(f64, f64)] {
+        match variable {
+            ChartVariable::WaveHeight => &self.wave_height,
+            ChartVariable::WindSpeed => &self.wind_speed,
+            ChartVariable::Pressure => &self.pressure,
+        }
+    }
+}
+
+/// Parses an NDBC `realtime2/<station>.txt` standard meteorological file.
+/// Rows are newest-first and roughly one hour apart, so the row index is
+/// used as an approximation of hours-ago rather than parsing the YY/MM/DD
+/// hh/mm columns into a real timestamp.
+fn parse_realtime(body: String) -> RealtimeSeries {
+    let mut series = RealtimeSeries::default();
+    for (i, line) in body.lines().filter(|line| !line.starts_with('#')).enumerate() {
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        if cols.len() < 19 {
+            continue;
+        }
+        let hours_ago = i as f64;
+        if let Some(wvht) = parse_field(cols[8]) {
+            series.wave_height.push((hours_ago, wvht));
+        }
+        if let Some(wspd) = parse_field(cols[6]) {
+            series.wind_speed.push((hours_ago, wspd));
+        }
+        if let Some(pres) = parse_field(cols[12]) {
+            series.pressure.push((hours_ago, pres));
+        }
+    }
+    series
+}
+
+fn draw_chart<B: tui::backend::Backend>(
+    f: &mut tui::terminal::Frame<B>,
+    area: tui::layout::Rect,
+    series: &RealtimeSeries,
+    variable: ChartVariable,
+) {
+    let data = series.series_for(variable);
+    let max_y = data.iter().map(|(_, y)| *y).fold(0.0_f64, f64::max);
+    let max_x = data.iter().map(|(x, _)| *x).fold(0.0_f64, f64::max);
+
+    let datasets = vec![Dataset::default()
+        .name(variable.label())
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(Color::LightCyan))
+        .data(data)];
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("{} ('v' to toggle)", variable.label())),
+        )
+        .x_axis(
+            Axis::default()
+                .title("hours ago")
+                .bounds([0.0, max_x])
+                .labels(vec!["now".into(), format!("{:.0}h", max_x).into()]),
+        )
+        .y_axis(
+            Axis::default()
+                .title(variable.label())
+                .bounds([0.0, max_y * 1.1 + 0.1])
+                .labels(vec!["0".into(), format!("{:.1}", max_y).into()]),
+        );
+    f.render_widget(chart, area);
+}
+
+/// Straight-line lon/lat distance from a station to the map cursor, used to
+/// resolve Enter to "the station nearest the crosshair".
+fn map_distance(station: &ActiveStation, (cursor_lon, cursor_lat): (f64, f64)) -> f64 {
+    ((station.lon - cursor_lon).powi(2) + (station.lat - cursor_lat).powi(2)).sqrt()
+}
+
+fn draw_map<B: tui::backend::Backend>(
+    f: &mut tui::terminal::Frame<B>,
+    area: tui::layout::Rect,
+    stations: &[ActiveStation],
+    selected_id: Option<&str>,
+    viewport: &MapViewport,
+) {
+    let canvas = Canvas::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Map (arrows pan, +/- zoom, enter selects)"),
+        )
+        .x_bounds(viewport.x_bounds())
+        .y_bounds(viewport.y_bounds())
+        .paint(|ctx| {
+            ctx.draw(&canvas::Map {
+                resolution: canvas::MapResolution::High,
+                color: Color::DarkGray,
+            });
+            for station in stations {
+                let color = if Some(station.id.as_str()) == selected_id {
+                    Color::LightCyan
+                } else {
+                    Color::White
+                };
+                ctx.print(station.lon, station.lat, Span::styled("o", Style::default().fg(color)));
+            }
+
+            let (cursor_lon, cursor_lat) = viewport.center();
+            ctx.print(cursor_lon, cursor_lat, Span::styled("+", Style::default().fg(Color::Yellow)));
+        });
+    f.render_widget(canvas, area);
+}
+
+/// Case-insensitive subsequence match: every character of `query` must
+/// appear in `text` in order, though not necessarily contiguously. This is
+/// the same loose matching used by fuzzy pickers in other TUIs.
+fn fuzzy_match(query: &str, text: &str) -> bool {
+    let text_lower = text.to_lowercase();
+    let mut chars = text_lower.chars();
+    query.to_lowercase().chars().all(|qc| chars.any(|c| c == qc))
+}
+
+/// Wraps the id/name columns in `*...*` when they're the reason a row
+/// survived the current filter, so the match is visible in the table.
+fn highlight_matched_fields(query: &str, row: &[String]) -> Vec<String> {
+    if query.is_empty() {
+        return row.to_vec();
+    }
+    row.iter()
+        .enumerate()
+        .map(|(i, cell)| {
+            if (i == 0 || i == 1) && fuzzy_match(query, cell) {
+                format!("*{}*", cell)
+            } else {
+                cell.clone()
+            }
+        })
+        .collect()
+}
+
+fn draw_search_bar<B: tui::backend::Backend>(
+    f: &mut tui::terminal::Frame<B>,
+    area: tui::layout::Rect,
+    search_mode: bool,
+    query: &str,
+) {
+    let text = if search_mode {
+        format!("/{}", query)
+    } else if !query.is_empty() {
+        format!("/{} (esc to clear)", query)
+    } else {
+        String::from("/ to search")
+    };
+    let paragraph = Paragraph::new(text).style(Style::default().fg(Color::Yellow));
+    f.render_widget(paragraph, area);
+}
+
+fn draw_status_bar<B: tui::backend::Backend>(
+    f: &mut tui::terminal::Frame<B>,
+    area: tui::layout::Rect,
+    stations: &StationsSnapshot,
+    obs: &ObsSnapshot,
+    realtime: &RealtimeSnapshot,
+) {
+    let mut parts = Vec::new();
+
+    if stations.refreshing || obs.refreshing || realtime.refreshing {
+        parts.push(String::from("refreshing..."));
+    }
+
+    if let Some(updated) = stations.last_updated {
+        parts.push(format!("stations updated {}s ago", updated.elapsed().as_secs()));
+    }
+    if let Some(updated) = obs.last_updated {
+        parts.push(format!("obs updated {}s ago", updated.elapsed().as_secs()));
+    }
+
+    for error in [&stations.error, &obs.error, &realtime.error].iter().filter_map(|e| e.as_ref()) {
+        parts.push(format!("error: {}", error));
+    }
+
+    let status = if parts.is_empty() {
+        String::from("loading...")
+    } else {
+        parts.join(" | ")
+    };
+
+    let paragraph = Paragraph::new(status).style(Style::default().fg(Color::DarkGray));
+    f.render_widget(paragraph, area);
+}
+
+fn fmt_opt(value: Option<f64>) -> String {
+    match value {
+        Some(v) => v.to_string(),
+        None => String::from("--"),
+    }
+}
+
+pub struct LatestObservation {
+    stn: String,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    yy: Option<f64>,
+    mm: Option<f64>,
+    dd: Option<f64>,
+    hh: Option<f64>,
+    mn: Option<f64>,
+    wdir: Option<f64>,
+    wspd: Option<f64>,
+    gst: Option<f64>,
+    wvht: Option<f64>,
+    dpd: Option<f64>,
+    apd: Option<f64>,
+    mwd: Option<f64>,
+    pres: Option<f64>,
+    ptdy: Option<f64>,
+    atmp: Option<f64>,
+    wtmp: Option<f64>,
+    dewp: Option<f64>,
+    vis: Option<f64>,
+    tide: Option<f64>,
+}
+
+impl LatestObservation {
+    fn to_row(&self) -> Vec<String> {
+        vec![
+            self.stn.clone(),
+            fmt_opt(self.lat),
+            fmt_opt(self.lon),
+            fmt_opt(self.yy),
+            fmt_opt(self.mm),
+            fmt_opt(self.dd),
+            fmt_opt(self.hh),
+            fmt_opt(self.mn),
+            fmt_opt(self.wdir),
+            fmt_opt(self.wspd),
+            fmt_opt(self.gst),
+            fmt_opt(self.wvht),
+            fmt_opt(self.dpd),
+            fmt_opt(self.apd),
+            fmt_opt(self.mwd),
+            fmt_opt(self.pres),
+            fmt_opt(self.ptdy),
+            fmt_opt(self.atmp),
+            fmt_opt(self.wtmp),
+            fmt_opt(self.dewp),
+            fmt_opt(self.vis),
+            fmt_opt(self.tide),
+        ]
+    }
+}
+
+/// Parses the NDBC `latest_obs.txt` fixed-column table. The first two lines
+/// are a header row and a units row, both starting with `#`; the remainder
+/// is one whitespace-delimited record per station. The token `MM` marks a
+/// missing value and is parsed as `None`.
+fn parse_latest_obs(body: String) -> Vec<LatestObservation> {
+    body.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.len() < 22 {
+                return None;
+            }
+            Some(LatestObservation {
+                stn: cols[0].to_owned(),
+                lat: parse_field(cols[1]),
+                lon: parse_field(cols[2]),
+                yy: parse_field(cols[3]),
+                mm: parse_field(cols[4]),
+                dd: parse_field(cols[5]),
+                hh: parse_field(cols[6]),
+                mn: parse_field(cols[7]),
+                wdir: parse_field(cols[8]),
+                wspd: parse_field(cols[9]),
+                gst: parse_field(cols[10]),
+                wvht: parse_field(cols[11]),
+                dpd: parse_field(cols[12]),
+                apd: parse_field(cols[13]),
+                mwd: parse_field(cols[14]),
+                pres: parse_field(cols[15]),
+                ptdy: parse_field(cols[16]),
+                atmp: parse_field(cols[17]),
+                wtmp: parse_field(cols[18]),
+                dewp: parse_field(cols[19]),
+                vis: parse_field(cols[20]),
+                tide: parse_field(cols[21]),
+            })
+        })
+        .collect()
+}
+
+fn parse_field(raw: &str) -> Option<f64> {
+    if raw == "MM" {
+        None
+    } else {
+        raw.parse::<f64>().ok()
+    }
+}
+
+fn xml_to_stations(body: String) -> Result<Vec<ActiveStation>, String> {
+    let doc = roxmltree::Document::parse(&body).map_err(|e| e.to_string())?;
+    doc.descendants()
+        .filter(|n| n.tag_name().name() == "station")
+        .map(ActiveStation::from_node)
+        .collect()
 }
 
 struct ActiveStation{
     id: String,
     name: String,
-    lat: String,
-    lon: String,
+    lat: f64,
+    lon: f64,
     program: String,
     kind: String,
     met: String,
@@ -208,19 +891,28 @@ struct ActiveStation{
 }
 
 impl ActiveStation{
-    fn from_node(node: Node)-> ActiveStation{
-        ActiveStation{
-            id: node.attribute("id").map_or(String::from("whew, no id? how'd that happen"), |a| a.to_owned()),
+    fn from_node(node: Node)-> Result<ActiveStation, String> {
+        let id = node.attribute("id").map_or(String::from("whew, no id? how'd that happen"), |a| a.to_owned());
+        let lat = node
+            .attribute("lat")
+            .and_then(|a| a.parse().ok())
+            .ok_or_else(|| format!("station {} has a non-numeric or missing lat", id))?;
+        let lon = node
+            .attribute("lon")
+            .and_then(|a| a.parse().ok())
+            .ok_or_else(|| format!("station {} has a non-numeric or missing lon", id))?;
+        Ok(ActiveStation{
+            id,
             name: node.attribute("name").map_or(String::from("whew, no name? how'd that happen"), |a| a.to_owned()),
-            lat: node.attribute("lat").map(|a| a.to_owned()).unwrap(),
-            lon: node.attribute("lon").map(|a| a.to_owned()).unwrap(),
+            lat,
+            lon,
             program: node.attribute("pgm").map_or(String::from("whew, no pgm? how'd that happen"), |a| a.to_owned()),
             kind: node.attribute("type").map_or(String::from("whew, no kind? how'd that happen"), |a| a.to_owned()),
             met: node.attribute("met").map_or(String::from("n"),|a| a.to_owned()),
             currents: node.attribute("currents").map_or(String::from("n"),|a| a.to_owned()),
             water_quality: node.attribute("waterquality").map_or(String::from("n"),|a| a.to_owned()),
             dart: node.attribute("dart").map_or(String::from("n"),|a| a.to_owned()),
-        }
+        })
     }
 
     fn to_row(&self)->Vec<String>{
@@ -8,4 +8,12 @@ pub async fn get_latest_obs() -> Result<Response, Error> {
 
 pub async fn get_active_stations() -> Result<Response, Error> {
     Ok(reqwest::get("https://www.ndbc.noaa.gov/activestations.xml").await?)
+}
+
+pub async fn get_station_realtime(id: &str) -> Result<Response, Error> {
+    Ok(reqwest::get(&format!(
+        "https://www.ndbc.noaa.gov/data/realtime2/{}.txt",
+        id
+    ))
+    .await?)
 }
\ No newline at end of file
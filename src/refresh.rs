@@ -0,0 +1,209 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+use tokio::sync::watch;
+
+use crate::{net, parse_latest_obs, parse_realtime, storage, xml_to_stations, ActiveStation, LatestObservation, RealtimeSeries};
+
+/// Default polling cadence for all background workers. Overridable per-run
+/// via the `TUOY_REFRESH_INTERVAL_SECS` environment variable, e.g. to poll
+/// less often on a metered connection.
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+fn refresh_interval() -> Duration {
+    std::env::var("TUOY_REFRESH_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_REFRESH_INTERVAL)
+}
+
+#[derive(Default)]
+pub struct StationsSnapshot {
+    pub stations: Vec<ActiveStation>,
+    pub last_updated: Option<Instant>,
+    pub error: Option<String>,
+    pub refreshing: bool,
+}
+
+#[derive(Default)]
+pub struct ObsSnapshot {
+    pub observations: Vec<LatestObservation>,
+    pub last_updated: Option<Instant>,
+    pub error: Option<String>,
+    pub refreshing: bool,
+}
+
+#[derive(Default)]
+pub struct RealtimeSnapshot {
+    pub series: RealtimeSeries,
+    pub last_updated: Option<Instant>,
+    pub error: Option<String>,
+    pub refreshing: bool,
+}
+
+pub fn spawn_stations_refresher(tx: watch::Sender<StationsSnapshot>) {
+    tokio::spawn(async move {
+        loop {
+            tx.send_modify(|s| s.refreshing = true);
+            let result = fetch_stations().await;
+            tx.send_modify(|s| {
+                s.refreshing = false;
+                match result {
+                    Ok(stations) => {
+                        s.stations = stations;
+                        s.last_updated = Some(Instant::now());
+                        s.error = None;
+                    }
+                    Err(e) => s.error = Some(e),
+                }
+            });
+            tokio::time::sleep(refresh_interval()).await;
+        }
+    });
+}
+
+async fn fetch_stations() -> Result<Vec<ActiveStation>, String> {
+    let resp = net::get_active_stations().await.map_err(|e| e.to_string())?;
+    let body = resp.text().await.map_err(|e| e.to_string())?;
+    xml_to_stations(body)
+}
+
+/// Polls `latest_obs.txt` on the configured interval and, on success, both
+/// publishes the parsed snapshot and persists it to `db` so the app keeps a
+/// history across runs instead of only ever knowing the latest reading.
+/// `db` is `None` when the local store couldn't be opened, in which case
+/// persistence is skipped and only the live snapshot is published.
+pub fn spawn_obs_refresher(tx: watch::Sender<ObsSnapshot>, db: Option<Arc<Mutex<Connection>>>) {
+    tokio::spawn(async move {
+        loop {
+            tx.send_modify(|s| s.refreshing = true);
+            let result = fetch_obs().await;
+            if let (Ok(observations), Some(db)) = (&result, &db) {
+                if let Ok(conn) = db.lock() {
+                    if let Err(e) = storage::insert_observations(&conn, observations) {
+                        eprintln!("failed to persist observations: {}", e);
+                    }
+                }
+            }
+            tx.send_modify(|s| {
+                s.refreshing = false;
+                match result {
+                    Ok(observations) => {
+                        s.observations = observations;
+                        s.last_updated = Some(Instant::now());
+                        s.error = None;
+                    }
+                    Err(e) => s.error = Some(e),
+                }
+            });
+            tokio::time::sleep(refresh_interval()).await;
+        }
+    });
+}
+
+async fn fetch_obs() -> Result<Vec<LatestObservation>, String> {
+    let resp = net::get_latest_obs().await.map_err(|e| e.to_string())?;
+    let body = resp.text().await.map_err(|e| e.to_string())?;
+    Ok(parse_latest_obs(body))
+}
+
+/// Refetches the selected station's realtime series whenever `id_rx` changes
+/// and on every refresh-interval tick, whichever comes first.
+pub fn spawn_realtime_refresher(
+    mut id_rx: watch::Receiver<Option<String>>,
+    tx: watch::Sender<RealtimeSnapshot>,
+) {
+    tokio::spawn(async move {
+        loop {
+            let id = id_rx.borrow().clone();
+            if let Some(id) = id {
+                tx.send_modify(|s| s.refreshing = true);
+                let result = fetch_realtime(&id).await;
+                tx.send_modify(|s| {
+                    s.refreshing = false;
+                    match result {
+                        Ok(series) => {
+                            s.series = series;
+                            s.last_updated = Some(Instant::now());
+                            s.error = None;
+                        }
+                        Err(e) => s.error = Some(e),
+                    }
+                });
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(refresh_interval()) => {}
+                _ = id_rx.changed() => {}
+            }
+        }
+    });
+}
+
+async fn fetch_realtime(id: &str) -> Result<RealtimeSeries, String> {
+    let resp = net::get_station_realtime(id).await.map_err(|e| e.to_string())?;
+    let body = resp.text().await.map_err(|e| e.to_string())?;
+    Ok(parse_realtime(body))
+}
+
+/// Mirrors `spawn_realtime_refresher`, but reads the selected station's
+/// history out of `db` instead of hitting NDBC, so the chart still has
+/// something to plot offline or before the first live fetch lands. Does
+/// nothing if `db` is `None`, i.e. the local store couldn't be opened.
+pub fn spawn_history_refresher(
+    mut id_rx: watch::Receiver<Option<String>>,
+    db: Option<Arc<Mutex<Connection>>>,
+    tx: watch::Sender<RealtimeSnapshot>,
+) {
+    tokio::spawn(async move {
+        loop {
+            let id = id_rx.borrow().clone();
+            if let (Some(id), Some(db)) = (id, &db) {
+                let result = fetch_history(db, &id);
+                tx.send_modify(|s| {
+                    match result {
+                        Ok(series) => {
+                            s.series = series;
+                            s.last_updated = Some(Instant::now());
+                            s.error = None;
+                        }
+                        Err(e) => s.error = Some(e),
+                    }
+                });
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(refresh_interval()) => {}
+                _ = id_rx.changed() => {}
+            }
+        }
+    });
+}
+
+fn fetch_history(db: &Arc<Mutex<Connection>>, id: &str) -> Result<RealtimeSeries, String> {
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    let rows = storage::recent_for_station(&conn, id, "").map_err(|e| e.to_string())?;
+    Ok(history_to_series(rows))
+}
+
+/// Stored rows come back oldest-first; reverse them so index 0 is the most
+/// recent, matching `parse_realtime`'s hours-ago convention for the x axis.
+fn history_to_series(mut rows: Vec<storage::StoredObservation>) -> RealtimeSeries {
+    rows.reverse();
+    let mut series = RealtimeSeries::default();
+    for (i, row) in rows.iter().enumerate() {
+        let hours_ago = i as f64;
+        if let Some(wvht) = row.wvht {
+            series.wave_height.push((hours_ago, wvht));
+        }
+        if let Some(wspd) = row.wspd {
+            series.wind_speed.push((hours_ago, wspd));
+        }
+        if let Some(pres) = row.pres {
+            series.pressure.push((hours_ago, pres));
+        }
+    }
+    series
+}
@@ -0,0 +1,71 @@
+/// Tracks the visible window onto the station map: a center point in
+/// lon/lat degrees and a zoom multiplier applied to the default
+/// whole-world bounds.
+pub struct MapViewport {
+    center_lon: f64,
+    center_lat: f64,
+    zoom: f64,
+}
+
+impl MapViewport {
+    pub fn new() -> MapViewport {
+        MapViewport {
+            center_lon: 0.0,
+            center_lat: 0.0,
+            zoom: 1.0,
+        }
+    }
+
+    pub fn x_bounds(&self) -> [f64; 2] {
+        let half = 180.0 / self.zoom;
+        [self.center_lon - half, self.center_lon + half]
+    }
+
+    pub fn y_bounds(&self) -> [f64; 2] {
+        let half = 90.0 / self.zoom;
+        [self.center_lat - half, self.center_lat + half]
+    }
+
+    pub fn pan(&mut self, dlon: f64, dlat: f64) {
+        let step = 10.0 / self.zoom;
+        self.center_lon += dlon * step;
+        self.center_lat += dlat * step;
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.zoom = (self.zoom * 1.25).min(16.0);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom = (self.zoom / 1.25).max(1.0);
+    }
+
+    /// The lon/lat the canvas is currently centered on — also where the
+    /// crosshair is drawn, and what Enter resolves to the nearest station of.
+    pub fn center(&self) -> (f64, f64) {
+        (self.center_lon, self.center_lat)
+    }
+}
+
+pub struct TabsState {
+    pub titles: Vec<&'static str>,
+    pub index: usize,
+}
+
+impl TabsState {
+    pub fn new(titles: Vec<&'static str>) -> TabsState {
+        TabsState { titles, index: 0 }
+    }
+
+    pub fn next(&mut self) {
+        self.index = (self.index + 1) % self.titles.len();
+    }
+
+    pub fn previous(&mut self) {
+        if self.index == 0 {
+            self.index = self.titles.len() - 1;
+        } else {
+            self.index -= 1;
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::path::PathBuf;
+
+use rusqlite::{params, Connection, Result as SqlResult};
+
+use crate::LatestObservation;
+
+/// `$XDG_DATA_HOME/tuoy/observations.sqlite3` (or the platform equivalent),
+/// falling back to the current directory if no data dir can be found.
+pub fn default_db_path() -> PathBuf {
+    let mut dir = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+    dir.push("tuoy");
+    dir.push("observations.sqlite3");
+    dir
+}
+
+pub fn open(path: &PathBuf) -> SqlResult<Connection> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS observations (
+            station     TEXT NOT NULL,
+            observed_at TEXT NOT NULL,
+            wdir REAL, wspd REAL, gst  REAL, wvht REAL,
+            dpd  REAL, apd  REAL, mwd  REAL, pres REAL,
+            ptdy REAL, atmp REAL, wtmp REAL, dewp REAL,
+            vis  REAL, tide REAL,
+            PRIMARY KEY (station, observed_at)
+        )",
+        [],
+    )?;
+    Ok(conn)
+}
+
+/// Inserts each observation, keyed on (station, observed_at) so repeated
+/// polls of the same NDBC snapshot don't create duplicate rows.
+pub fn insert_observations(conn: &Connection, observations: &[LatestObservation]) -> SqlResult<()> {
+    for obs in observations {
+        conn.execute(
+            "INSERT OR IGNORE INTO observations
+                (station, observed_at, wdir, wspd, gst, wvht, dpd, apd, mwd, pres, ptdy, atmp, wtmp, dewp, vis, tide)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+            params![
+                obs.stn,
+                observed_at_key(obs),
+                obs.wdir,
+                obs.wspd,
+                obs.gst,
+                obs.wvht,
+                obs.dpd,
+                obs.apd,
+                obs.mwd,
+                obs.pres,
+                obs.ptdy,
+                obs.atmp,
+                obs.wtmp,
+                obs.dewp,
+                obs.vis,
+                obs.tide,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+pub struct StoredObservation {
+    pub observed_at: String,
+    pub wvht: Option<f64>,
+    pub wspd: Option<f64>,
+    pub pres: Option<f64>,
+}
+
+/// Rows for `station` at or after `since` (an `observed_at`-formatted key),
+/// oldest first, so the chart view can plot them left-to-right.
+pub fn recent_for_station(conn: &Connection, station: &str, since: &str) -> SqlResult<Vec<StoredObservation>> {
+    let mut stmt = conn.prepare(
+        "SELECT observed_at, wvht, wspd, pres FROM observations
+         WHERE station = ?1 AND observed_at >= ?2
+         ORDER BY observed_at ASC",
+    )?;
+    let rows = stmt
+        .query_map(params![station, since], |row| {
+            Ok(StoredObservation {
+                observed_at: row.get(0)?,
+                wvht: row.get(1)?,
+                wspd: row.get(2)?,
+                pres: row.get(3)?,
+            })
+        })?
+        .collect();
+    rows
+}
+
+fn observed_at_key(obs: &LatestObservation) -> String {
+    format!(
+        "{:04.0}-{:02.0}-{:02.0} {:02.0}:{:02.0}",
+        obs.yy.unwrap_or(0.0),
+        obs.mm.unwrap_or(0.0),
+        obs.dd.unwrap_or(0.0),
+        obs.hh.unwrap_or(0.0),
+        obs.mn.unwrap_or(0.0),
+    )
+}